@@ -0,0 +1,707 @@
+//! The lock-free core behind the public `Worker`/`Stealer`/`Injector` wrappers in `std_deque`.
+//!
+//! `Worker`/`Stealer` implement the classic Chase-Lev work-stealing deque: a single owning
+//! thread pushes and pops at the `bottom` index while any number of thieves race to steal from
+//! `top`, growing into a fresh, epoch-reclaimed buffer as needed. `Injector` is the multi-producer
+//! entry point shared by the whole pool; since many threads may push concurrently, it is backed
+//! by a plain mutex-protected queue rather than the single-producer ring buffer, and only needs
+//! an epoch handle when a steal writes stolen tasks into a `Worker`'s epoch-protected buffer.
+
+use std::cmp;
+use std::collections::VecDeque;
+use std::fmt;
+use std::iter::FromIterator;
+use std::mem;
+use std::ptr;
+use std::sync::atomic::{self, AtomicIsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use epoch::{self, Atomic, Guard, Handle, Owned};
+
+/// Smallest allocated buffer capacity for a `Worker`'s ring buffer.
+const MIN_CAP: usize = 64;
+
+/// The result of a steal operation.
+pub enum Steal<T> {
+    /// The queue was empty at the time of the attempt.
+    Empty,
+    /// Some other thread won a race for the same task(s); the operation should be retried.
+    Retry,
+    /// The operation succeeded.
+    Success(T),
+}
+
+impl<T> Steal<T> {
+    /// Returns `true` if the queue was found empty.
+    pub fn is_empty(&self) -> bool {
+        match *self {
+            Steal::Empty => true,
+            _ => false,
+        }
+    }
+
+    /// Returns `true` if the operation should be retried.
+    pub fn is_retry(&self) -> bool {
+        match *self {
+            Steal::Retry => true,
+            _ => false,
+        }
+    }
+
+    /// Returns `true` if the operation succeeded.
+    pub fn is_success(&self) -> bool {
+        match *self {
+            Steal::Success(_) => true,
+            _ => false,
+        }
+    }
+
+    /// Converts `self` into `Option<T>`, discarding whether an empty queue or a retry was seen.
+    pub fn success(self) -> Option<T> {
+        match self {
+            Steal::Success(t) => Some(t),
+            _ => None,
+        }
+    }
+
+    /// Returns `self` if it is a success, and `f()` otherwise, propagating a `Retry` past an
+    /// `Empty` result from `f` so that callers don't mistake "this particular source had nothing"
+    /// for "there is nothing left to retry".
+    pub fn or_else<F>(self, f: F) -> Steal<T>
+    where
+        F: FnOnce() -> Steal<T>,
+    {
+        match self {
+            Steal::Empty => f(),
+            Steal::Success(_) => self,
+            Steal::Retry => match f() {
+                Steal::Success(t) => Steal::Success(t),
+                _ => Steal::Retry,
+            },
+        }
+    }
+}
+
+impl<T> PartialEq for Steal<T>
+where
+    T: PartialEq,
+{
+    fn eq(&self, other: &Steal<T>) -> bool {
+        match (self, other) {
+            (&Steal::Empty, &Steal::Empty) | (&Steal::Retry, &Steal::Retry) => true,
+            (&Steal::Success(ref a), &Steal::Success(ref b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl<T> Eq for Steal<T> where T: Eq {}
+
+impl<T> fmt::Debug for Steal<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Steal::Empty => f.pad("Empty"),
+            Steal::Retry => f.pad("Retry"),
+            Steal::Success(_) => f.pad("Success(..)"),
+        }
+    }
+}
+
+/// Collects a sequence of steal attempts into one: the first success wins, otherwise the result
+/// is `Retry` if any attempt asked for a retry, and `Empty` only if every attempt was empty.
+impl<T> FromIterator<Steal<T>> for Steal<T> {
+    fn from_iter<I>(iter: I) -> Steal<T>
+    where
+        I: IntoIterator<Item = Steal<T>>,
+    {
+        let mut retry = false;
+        for s in iter {
+            match s {
+                Steal::Success(_) => return s,
+                Steal::Retry => retry = true,
+                Steal::Empty => {}
+            }
+        }
+        if retry {
+            Steal::Retry
+        } else {
+            Steal::Empty
+        }
+    }
+}
+
+/// Which end of the buffer a `Worker`'s own `pop` takes from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Flavor {
+    /// Pops from the `bottom`, the same end tasks are pushed onto.
+    Lifo,
+    /// Pops from the `top`, the same end stealers take from.
+    Fifo,
+}
+
+/// A plain heap-allocated ring buffer of power-of-two capacity. Indices wrap modulo `cap`.
+struct Buffer<T> {
+    ptr: *mut T,
+    cap: usize,
+}
+
+unsafe impl<T> Send for Buffer<T> {}
+
+impl<T> Buffer<T> {
+    fn alloc(cap: usize) -> Buffer<T> {
+        debug_assert_eq!(cap.count_ones(), 1, "capacity must be a power of two");
+        let mut v = Vec::with_capacity(cap);
+        let ptr = v.as_mut_ptr();
+        mem::forget(v);
+        Buffer { ptr, cap }
+    }
+
+    unsafe fn dealloc(self) {
+        drop(Vec::from_raw_parts(self.ptr, 0, self.cap));
+    }
+
+    fn at(&self, index: isize) -> *mut T {
+        unsafe { self.ptr.offset(index & (self.cap as isize - 1)) }
+    }
+
+    unsafe fn write(&self, index: isize, task: T) {
+        ptr::write(self.at(index), task)
+    }
+
+    unsafe fn read(&self, index: isize) -> T {
+        ptr::read(self.at(index))
+    }
+
+    /// Allocates a buffer of `new_cap` and moves the live range `[top, bottom)` into it.
+    unsafe fn grow(&self, bottom: isize, top: isize, new_cap: usize) -> Buffer<T> {
+        let new = Buffer::alloc(new_cap);
+        let mut i = top;
+        while i != bottom {
+            new.write(i, self.read(i));
+            i = i.wrapping_add(1);
+        }
+        new
+    }
+}
+
+/// State shared between a `Worker` and all of its `Stealer`s.
+struct Inner<T> {
+    /// Index of the next slot to push to / pop from (for LIFO).
+    bottom: AtomicIsize,
+    /// Index of the next slot to steal from / pop from (for FIFO).
+    top: AtomicIsize,
+    /// The current backing buffer. Replaced (and the old one reclaimed through the epoch) when
+    /// the queue outgrows it.
+    buffer: Atomic<Buffer<T>>,
+}
+
+impl<T> Inner<T> {
+    fn new() -> Inner<T> {
+        Inner {
+            bottom: AtomicIsize::new(0),
+            top: AtomicIsize::new(0),
+            buffer: Atomic::new(Buffer::alloc(MIN_CAP)),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        let b = self.bottom.load(Ordering::SeqCst);
+        let t = self.top.load(Ordering::SeqCst);
+        t.wrapping_sub(b) >= 0
+    }
+
+    /// An approximation of the number of queued tasks, saturating to `0` under a race that would
+    /// otherwise make `top` appear ahead of `bottom`.
+    fn len(&self) -> usize {
+        let b = self.bottom.load(Ordering::SeqCst);
+        let t = self.top.load(Ordering::SeqCst);
+        cmp::max(b.wrapping_sub(t), 0) as usize
+    }
+
+    /// Grows the buffer if it cannot fit `additional` more tasks on top of the live range
+    /// `[top, bottom)`. Only ever called by the single thread that owns this `Inner` as a
+    /// `Worker`, or on its behalf by a steal writing into it.
+    unsafe fn reserve(&self, bottom: isize, top: isize, additional: usize, guard: &Guard) {
+        let buffer = self.buffer.load(Ordering::Relaxed, guard);
+        let cap = buffer.deref().cap;
+
+        if (bottom.wrapping_sub(top) as usize) + additional > cap {
+            let new_cap = cmp::max(MIN_CAP, cap * 2)
+                .max(((bottom.wrapping_sub(top) as usize) + additional).next_power_of_two());
+            let new = buffer.deref().grow(bottom, top, new_cap);
+            let old = self.buffer.swap(Owned::new(new), Ordering::Release, guard);
+            guard.defer_destroy(old);
+        }
+    }
+}
+
+impl<T> Drop for Inner<T> {
+    fn drop(&mut self) {
+        // No other thread can be touching this queue anymore: it is only reachable through the
+        // `Arc` that a `Worker` and its `Stealer`s share, and we are the last owner.
+        unsafe {
+            let guard = &epoch::unprotected();
+            let mut top = self.top.load(Ordering::Relaxed);
+            let bottom = self.bottom.load(Ordering::Relaxed);
+            let buffer = self.buffer.load(Ordering::Relaxed, guard);
+
+            while top != bottom {
+                ptr::drop_in_place(buffer.deref().at(top));
+                top = top.wrapping_add(1);
+            }
+
+            buffer.into_owned().into_box().dealloc();
+        }
+    }
+}
+
+/// A worker queue: the single-producer, single-consumer, multi-stealer end of a deque.
+pub struct Worker<T> {
+    inner: Arc<Inner<T>>,
+    flavor: Flavor,
+}
+
+unsafe impl<T: Send> Send for Worker<T> {}
+
+impl<T> Worker<T> {
+    pub fn new_fifo() -> Worker<T> {
+        Worker {
+            inner: Arc::new(Inner::new()),
+            flavor: Flavor::Fifo,
+        }
+    }
+
+    pub fn new_lifo() -> Worker<T> {
+        Worker {
+            inner: Arc::new(Inner::new()),
+            flavor: Flavor::Lifo,
+        }
+    }
+
+    pub fn stealer(&self) -> Stealer<T> {
+        Stealer {
+            inner: self.inner.clone(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    pub fn push(&self, task: T, handle: &Handle) {
+        let guard = handle.pin();
+        self.push_pinned(task, &guard)
+    }
+
+    /// Same as `push`, but reuses an already-pinned `guard` instead of pinning a fresh one.
+    pub fn push_pinned(&self, task: T, guard: &Guard) {
+        let b = self.inner.bottom.load(Ordering::Relaxed);
+        let t = self.inner.top.load(Ordering::Acquire);
+
+        unsafe {
+            self.inner.reserve(b, t, 1, guard);
+            let buffer = self.inner.buffer.load(Ordering::Relaxed, guard);
+            buffer.deref().write(b, task);
+        }
+
+        atomic::fence(Ordering::Release);
+        self.inner.bottom.store(b.wrapping_add(1), Ordering::Release);
+    }
+
+    pub fn pop(&self, handle: &Handle) -> Option<T> {
+        let guard = handle.pin();
+        self.pop_pinned(&guard)
+    }
+
+    /// Same as `pop`, but reuses an already-pinned `guard` instead of pinning a fresh one.
+    pub fn pop_pinned(&self, guard: &Guard) -> Option<T> {
+        match self.flavor {
+            Flavor::Fifo => loop {
+                match Stealer::steal_from(&self.inner, guard) {
+                    Steal::Empty => return None,
+                    Steal::Retry => continue,
+                    Steal::Success(task) => return Some(task),
+                }
+            },
+            Flavor::Lifo => unsafe {
+                let b = self.inner.bottom.load(Ordering::Relaxed);
+                if b == self.inner.top.load(Ordering::Relaxed) {
+                    return None;
+                }
+                let b = b.wrapping_sub(1);
+                self.inner.bottom.store(b, Ordering::Relaxed);
+                atomic::fence(Ordering::SeqCst);
+
+                let t = self.inner.top.load(Ordering::Relaxed);
+                if t > b {
+                    self.inner.bottom.store(b.wrapping_add(1), Ordering::Relaxed);
+                    return None;
+                }
+
+                let buffer = self.inner.buffer.load(Ordering::Relaxed, guard);
+                let mut task = Some(buffer.deref().read(b));
+
+                if t == b {
+                    if self
+                        .inner
+                        .top
+                        .compare_exchange(
+                            t,
+                            t.wrapping_add(1),
+                            Ordering::SeqCst,
+                            Ordering::Relaxed,
+                        )
+                        .is_err()
+                    {
+                        mem::forget(task.take());
+                    }
+                    self.inner.bottom.store(b.wrapping_add(1), Ordering::Relaxed);
+                }
+
+                task
+            },
+        }
+    }
+}
+
+impl<T> fmt::Debug for Worker<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad("Worker { .. }")
+    }
+}
+
+/// A stealer handle, cloneable and shareable among threads.
+pub struct Stealer<T> {
+    inner: Arc<Inner<T>>,
+}
+
+impl<T> Clone for Stealer<T> {
+    fn clone(&self) -> Stealer<T> {
+        Stealer {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+unsafe impl<T: Send> Send for Stealer<T> {}
+unsafe impl<T: Send> Sync for Stealer<T> {}
+
+impl<T> Stealer<T> {
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    pub fn steal(&self, handle: &Handle) -> Steal<T> {
+        let guard = handle.pin();
+        self.steal_pinned(&guard)
+    }
+
+    /// Same as `steal`, but reuses an already-pinned `guard` instead of pinning a fresh one.
+    pub fn steal_pinned(&self, guard: &Guard) -> Steal<T> {
+        Self::steal_from(&self.inner, guard)
+    }
+
+    fn steal_from(inner: &Inner<T>, guard: &Guard) -> Steal<T> {
+        let t = inner.top.load(Ordering::Acquire);
+        atomic::fence(Ordering::SeqCst);
+        let b = inner.bottom.load(Ordering::Acquire);
+
+        if t.wrapping_sub(b) >= 0 {
+            return Steal::Empty;
+        }
+
+        unsafe {
+            let buffer = inner.buffer.load(Ordering::Acquire, guard);
+            let task = buffer.deref().read(t);
+
+            match inner.top.compare_exchange_weak(
+                t,
+                t.wrapping_add(1),
+                Ordering::SeqCst,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => Steal::Success(task),
+                Err(_) => {
+                    mem::forget(task);
+                    Steal::Retry
+                }
+            }
+        }
+    }
+
+    /// Steals a batch of at most `limit` tasks, computed as `min(limit, (len + 1) / 2)` so that a
+    /// caller with no opinion on `limit` still only takes around half of the victim.
+    pub fn steal_batch_with_limit(
+        &self,
+        dest: &Worker<T>,
+        limit: usize,
+        handle: &Handle,
+    ) -> Steal<()> {
+        let guard = handle.pin();
+        self.steal_batch_with_limit_pinned(dest, limit, &guard)
+    }
+
+    /// Same as `steal_batch_with_limit`, but reuses an already-pinned `guard` instead of pinning a
+    /// fresh one.
+    pub fn steal_batch_with_limit_pinned(
+        &self,
+        dest: &Worker<T>,
+        limit: usize,
+        guard: &Guard,
+    ) -> Steal<()> {
+        if limit == 0 {
+            return Steal::Empty;
+        }
+
+        let mut t = self.inner.top.load(Ordering::Acquire);
+
+        loop {
+            atomic::fence(Ordering::SeqCst);
+            let b = self.inner.bottom.load(Ordering::Acquire);
+            let len = b.wrapping_sub(t);
+            if len <= 0 {
+                return Steal::Empty;
+            }
+
+            let batch = cmp::min(limit, (len as usize + 1) / 2);
+            if batch == 0 {
+                return Steal::Empty;
+            }
+
+            unsafe {
+                let src_buffer = self.inner.buffer.load(Ordering::Acquire, guard);
+                let dest_b = dest.inner.bottom.load(Ordering::Relaxed);
+                let dest_t = dest.inner.top.load(Ordering::Acquire);
+                dest.inner.reserve(dest_b, dest_t, batch, guard);
+                let dest_buffer = dest.inner.buffer.load(Ordering::Relaxed, guard);
+
+                for i in 0..batch as isize {
+                    let task = src_buffer.deref().read(t.wrapping_add(i));
+                    dest_buffer.deref().write(dest_b.wrapping_add(i), task);
+                }
+
+                match self.inner.top.compare_exchange_weak(
+                    t,
+                    t.wrapping_add(batch as isize),
+                    Ordering::SeqCst,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => {
+                        atomic::fence(Ordering::Release);
+                        dest.inner
+                            .bottom
+                            .store(dest_b.wrapping_add(batch as isize), Ordering::Release);
+                        return Steal::Success(());
+                    }
+                    Err(cur) => {
+                        t = cur;
+                        continue;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Same as `steal_batch_with_limit`, but also pops one task out of `dest` to return directly,
+    /// so that batch and single-task stealing share one traversal of the victim.
+    pub fn steal_batch_and_pop_with_limit(
+        &self,
+        dest: &Worker<T>,
+        limit: usize,
+        handle: &Handle,
+    ) -> Steal<T> {
+        let guard = handle.pin();
+        self.steal_batch_and_pop_with_limit_pinned(dest, limit, &guard)
+    }
+
+    /// Same as `steal_batch_and_pop_with_limit`, but reuses an already-pinned `guard` instead of
+    /// pinning a fresh one.
+    pub fn steal_batch_and_pop_with_limit_pinned(
+        &self,
+        dest: &Worker<T>,
+        limit: usize,
+        guard: &Guard,
+    ) -> Steal<T> {
+        if limit == 0 {
+            return Steal::Empty;
+        }
+        let mut t = self.inner.top.load(Ordering::Acquire);
+
+        loop {
+            atomic::fence(Ordering::SeqCst);
+            let b = self.inner.bottom.load(Ordering::Acquire);
+            let len = b.wrapping_sub(t);
+            if len <= 0 {
+                return Steal::Empty;
+            }
+
+            let batch = cmp::min(limit, cmp::max((len as usize + 1) / 2, 1));
+
+            unsafe {
+                let src_buffer = self.inner.buffer.load(Ordering::Acquire, guard);
+                let popped = src_buffer.deref().read(t);
+                let moved = batch - 1;
+
+                let dest_b = dest.inner.bottom.load(Ordering::Relaxed);
+                if moved > 0 {
+                    let dest_t = dest.inner.top.load(Ordering::Acquire);
+                    dest.inner.reserve(dest_b, dest_t, moved, guard);
+                    let dest_buffer = dest.inner.buffer.load(Ordering::Relaxed, guard);
+                    for i in 0..moved as isize {
+                        let task = src_buffer.deref().read(t.wrapping_add(1 + i));
+                        dest_buffer.deref().write(dest_b.wrapping_add(i), task);
+                    }
+                }
+
+                match self.inner.top.compare_exchange_weak(
+                    t,
+                    t.wrapping_add(batch as isize),
+                    Ordering::SeqCst,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => {
+                        if moved > 0 {
+                            atomic::fence(Ordering::Release);
+                            dest.inner
+                                .bottom
+                                .store(dest_b.wrapping_add(moved as isize), Ordering::Release);
+                        }
+                        return Steal::Success(popped);
+                    }
+                    Err(cur) => {
+                        mem::forget(popped);
+                        t = cur;
+                        continue;
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<T> fmt::Debug for Stealer<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad("Stealer { .. }")
+    }
+}
+
+/// The multi-producer global injector queue.
+///
+/// Unlike `Worker`, many threads may push concurrently, so the injector is backed by a plain
+/// mutex-protected queue rather than a single-producer ring buffer. An epoch handle is only
+/// needed when stealing, since the stolen tasks are written into a `Worker`'s epoch-protected
+/// buffer.
+pub struct Injector<T> {
+    queue: Mutex<VecDeque<T>>,
+}
+
+impl<T> Injector<T> {
+    pub fn new() -> Injector<T> {
+        Injector {
+            queue: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    pub fn push(&self, task: T) {
+        self.queue.lock().unwrap().push_back(task);
+    }
+
+    pub fn steal(&self) -> Steal<T> {
+        match self.queue.lock().unwrap().pop_front() {
+            Some(task) => Steal::Success(task),
+            None => Steal::Empty,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.lock().unwrap().is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.queue.lock().unwrap().len()
+    }
+
+    pub fn steal_batch_with_limit(
+        &self,
+        dest: &Worker<T>,
+        limit: usize,
+        handle: &Handle,
+    ) -> Steal<()> {
+        let guard = handle.pin();
+        self.steal_batch_with_limit_pinned(dest, limit, &guard)
+    }
+
+    /// Same as `steal_batch_with_limit`, but reuses an already-pinned `guard` instead of pinning a
+    /// fresh one.
+    pub fn steal_batch_with_limit_pinned(
+        &self,
+        dest: &Worker<T>,
+        limit: usize,
+        guard: &Guard,
+    ) -> Steal<()> {
+        if limit == 0 {
+            return Steal::Empty;
+        }
+
+        let mut queue = self.queue.lock().unwrap();
+        if queue.is_empty() {
+            return Steal::Empty;
+        }
+
+        let batch = cmp::min(limit, cmp::max((queue.len() + 1) / 2, 1));
+        for task in queue.drain(..batch) {
+            dest.push_pinned(task, guard);
+        }
+        Steal::Success(())
+    }
+
+    pub fn steal_batch_and_pop_with_limit(
+        &self,
+        dest: &Worker<T>,
+        limit: usize,
+        handle: &Handle,
+    ) -> Steal<T> {
+        let guard = handle.pin();
+        self.steal_batch_and_pop_with_limit_pinned(dest, limit, &guard)
+    }
+
+    /// Same as `steal_batch_and_pop_with_limit`, but reuses an already-pinned `guard` instead of
+    /// pinning a fresh one.
+    pub fn steal_batch_and_pop_with_limit_pinned(
+        &self,
+        dest: &Worker<T>,
+        limit: usize,
+        guard: &Guard,
+    ) -> Steal<T> {
+        if limit == 0 {
+            return Steal::Empty;
+        }
+        let mut queue = self.queue.lock().unwrap();
+        let first = match queue.pop_front() {
+            Some(task) => task,
+            None => return Steal::Empty,
+        };
+
+        let batch = cmp::min(limit, cmp::max((queue.len() + 2) / 2, 1));
+        let moved = cmp::min(batch.saturating_sub(1), queue.len());
+        for task in queue.drain(..moved) {
+            dest.push_pinned(task, guard);
+        }
+
+        Steal::Success(first)
+    }
+}
+
+impl<T> fmt::Debug for Injector<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad("Injector { .. }")
+    }
+}
@@ -0,0 +1,146 @@
+use std::iter;
+
+use {Injector, Stealer, Worker};
+
+/// Default number of `find_task` calls per forced injector poll.
+const DEFAULT_TICK_INTERVAL: usize = 64;
+
+/// A `find_task` helper that guarantees the global injector gets a chance to run.
+///
+/// The `find_task` strategy shown in the module docs looks at the local queue first and only
+/// falls back to the injector and other stealers once it runs dry. Under a steady stream of local
+/// work that never empties the local queue (a tight LIFO producer/consumer loop, say), tasks
+/// sitting in the injector can starve indefinitely. `Scheduler` fixes this by counting calls to
+/// [`find_task`] and, once every `tick_interval` calls, checking the injector *before* the local
+/// queue.
+///
+/// [`find_task`]: struct.Scheduler.html#method.find_task
+///
+/// # Examples
+///
+/// ```
+/// use crossbeam_deque::{Injector, Scheduler, Worker};
+///
+/// let local = Worker::new_fifo();
+/// let global = Injector::new();
+/// let stealers = Vec::new();
+///
+/// global.push(1);
+///
+/// let mut scheduler = Scheduler::new(4);
+/// assert_eq!(scheduler.find_task(&local, &global, &stealers), Some(1));
+/// ```
+#[derive(Debug)]
+pub struct Scheduler {
+    tick: usize,
+    tick_interval: usize,
+}
+
+impl Scheduler {
+    /// Creates a scheduler that forces an injector poll once every `tick_interval` calls to
+    /// [`find_task`]. A `tick_interval` of `0` disables the forced poll.
+    ///
+    /// [`find_task`]: struct.Scheduler.html#method.find_task
+    pub fn new(tick_interval: usize) -> Scheduler {
+        Scheduler {
+            tick: 0,
+            tick_interval,
+        }
+    }
+
+    /// Finds the next task to run, with starvation guarantees for `global`.
+    ///
+    /// Mirrors the `find_task` example in the module docs: pop locally, then drain a batch from
+    /// the injector, then try each stealer in turn, retrying until some attempt succeeds or every
+    /// source reports empty. Every `tick_interval`th call instead checks the injector first, so
+    /// tasks queued there are guaranteed to be picked up even if the local queue never empties.
+    pub fn find_task<T>(
+        &mut self,
+        local: &Worker<T>,
+        global: &Injector<T>,
+        stealers: &[Stealer<T>],
+    ) -> Option<T> {
+        self.tick = self.tick.wrapping_add(1);
+
+        if self.tick_interval != 0 && self.tick % self.tick_interval == 0 {
+            if let Some(task) = Self::poll_injector(local, global) {
+                return Some(task);
+            }
+        }
+
+        local.pop().or_else(|| {
+            iter::repeat_with(|| {
+                global
+                    .steal_batch_and_pop(local)
+                    .or_else(|| stealers.iter().map(|s| s.steal()).collect())
+            })
+            .find(|s| !s.is_retry())
+            .and_then(|s| s.success())
+        })
+    }
+
+    /// Drains a batch from `global` into `local` and pops one task, retrying past spurious
+    /// failures.
+    fn poll_injector<T>(local: &Worker<T>, global: &Injector<T>) -> Option<T> {
+        iter::repeat_with(|| global.steal_batch_and_pop(local))
+            .find(|s| !s.is_retry())
+            .and_then(|s| s.success())
+    }
+}
+
+impl Default for Scheduler {
+    /// Creates a scheduler with the default tick interval.
+    fn default() -> Scheduler {
+        Scheduler::new(DEFAULT_TICK_INTERVAL)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Scheduler;
+    use {Injector, Worker};
+
+    #[test]
+    fn forces_injector_poll_on_tick_even_when_local_is_nonempty() {
+        let local = Worker::new_fifo();
+        let global = Injector::new();
+        let stealers = Vec::new();
+        let mut scheduler = Scheduler::new(3);
+
+        // Keep the local queue nonempty on every call. Only prime the injector right before a
+        // tick that should force a poll, with a value local could never produce, so the injector
+        // task surfacing proves the forced check ran instead of the usual local-first order.
+        for tick in 1..=6 {
+            local.push(-1);
+            if tick % 3 == 0 {
+                global.push(100 + tick);
+            }
+
+            let task = scheduler.find_task(&local, &global, &stealers);
+            if tick % 3 == 0 {
+                assert_eq!(
+                    task,
+                    Some(100 + tick),
+                    "tick {} should force an injector poll",
+                    tick
+                );
+            } else {
+                assert_eq!(task, Some(-1), "tick {} should pop locally", tick);
+            }
+        }
+    }
+
+    #[test]
+    fn zero_tick_interval_disables_forced_poll() {
+        let local = Worker::new_fifo();
+        let global = Injector::new();
+        let stealers = Vec::new();
+        let mut scheduler = Scheduler::new(0);
+
+        local.push(1);
+        global.push(2);
+
+        assert_eq!(scheduler.find_task(&local, &global, &stealers), Some(1));
+        assert_eq!(scheduler.find_task(&local, &global, &stealers), Some(2));
+    }
+}
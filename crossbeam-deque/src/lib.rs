@@ -107,5 +107,20 @@ mod std_deque;
 #[cfg(feature = "std")]
 pub use std_deque::*;
 
+#[cfg(feature = "std")]
+mod load_balancer;
+#[cfg(feature = "std")]
+pub use load_balancer::LoadBalancer;
+
+#[cfg(feature = "std")]
+mod fairness;
+#[cfg(feature = "std")]
+pub use fairness::Scheduler;
+
+#[cfg(feature = "std")]
+mod registry;
+#[cfg(feature = "std")]
+pub use registry::{RegistrationGuard, StealerRegistry};
+
 #[cfg(all(not(feature = "std"), feature = "nightly"))]
 pub use deque_impl::*;
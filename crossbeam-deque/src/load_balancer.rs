@@ -0,0 +1,148 @@
+use std::cmp;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use {Injector, Steal, Stealer, Worker};
+
+/// Shared context for proportional, load-aware stealing.
+///
+/// A `LoadBalancer` tracks the total number of tasks currently queued across every participating
+/// [`Worker`] and [`Injector`], along with how many workers are sharing the load. Routing
+/// `push`/`pop` through it keeps the total in sync, and [`Stealer::steal_proportional`] uses that
+/// total to steal only a victim's *surplus* over the system-wide average, rather than always
+/// grabbing half of it.
+///
+/// # Examples
+///
+/// ```
+/// use crossbeam_deque::{LoadBalancer, Worker};
+///
+/// let load = LoadBalancer::new(2);
+///
+/// let w1 = Worker::new_fifo();
+/// load.push(&w1, 1);
+///
+/// // With 1 task spread over 2 workers, `mean()` rounds down to 0, so `w1`'s single task counts
+/// // as surplus (1 > 0). But `n = min(len - mean, len / 2) = min(1, 0) = 0`, so there is nothing
+/// // to actually move yet.
+/// let w2 = Worker::new_fifo();
+/// assert!(w1.stealer().steal_proportional(&w2, &load).success().is_none());
+/// ```
+///
+/// [`Worker`]: struct.Worker.html
+/// [`Injector`]: struct.Injector.html
+/// [`Stealer::steal_proportional`]: struct.Stealer.html#method.steal_proportional
+#[derive(Debug)]
+pub struct LoadBalancer {
+    total: AtomicUsize,
+    worker_count: usize,
+}
+
+impl LoadBalancer {
+    /// Creates a load balancer shared by `worker_count` workers.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_deque::LoadBalancer;
+    ///
+    /// let load = LoadBalancer::new(4);
+    /// ```
+    pub fn new(worker_count: usize) -> LoadBalancer {
+        LoadBalancer {
+            total: AtomicUsize::new(0),
+            worker_count,
+        }
+    }
+
+    /// Pushes a task into `worker` and records it in the shared total.
+    pub fn push<T>(&self, worker: &Worker<T>, task: T) {
+        worker.push(task);
+        self.total.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Pops a task from `worker`, if any, and records it in the shared total.
+    pub fn pop<T>(&self, worker: &Worker<T>) -> Option<T> {
+        let task = worker.pop();
+        if task.is_some() {
+            self.total.fetch_sub(1, Ordering::SeqCst);
+        }
+        task
+    }
+
+    /// Pushes a task into the global `injector` and records it in the shared total.
+    pub fn push_injector<T>(&self, injector: &Injector<T>, task: T) {
+        injector.push(task);
+        self.total.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// The mean number of queued tasks per worker, as of the last recorded push/pop/steal.
+    ///
+    /// This is inherently approximate: concurrent activity on other workers may make `total`
+    /// stale by the time it is read here.
+    fn mean(&self) -> usize {
+        if self.worker_count == 0 {
+            0
+        } else {
+            self.total.load(Ordering::SeqCst) / self.worker_count
+        }
+    }
+
+    /// Records that one task has left the system (stolen and consumed, or popped elsewhere).
+    fn sub_one(&self) {
+        self.total.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl<T> Stealer<T> {
+    /// Steals the victim's surplus over the system-wide average, as tracked by `load`.
+    ///
+    /// Instead of always grabbing around half of the victim queue, this computes the mean queue
+    /// length across `load`'s workers and steals `min(len - mean, len / 2)` tasks, moving them
+    /// into `dest` and popping one to return. Only the popped task actually leaves the tracked
+    /// system — the rest are merely relocated into `dest`'s still-tracked queue — so `load`'s
+    /// total is decremented by one, not by the whole batch. If the victim is at or below the
+    /// mean, no tasks are stolen and [`Steal::Empty`] is returned. This keeps heavily loaded
+    /// queues as net donors while protecting lightly loaded ones from being drained
+    /// unnecessarily.
+    ///
+    /// [`Steal::Empty`]: enum.Steal.html#variant.Empty
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_deque::{LoadBalancer, Worker};
+    ///
+    /// let load = LoadBalancer::new(2);
+    ///
+    /// let w1 = Worker::new_fifo();
+    /// for i in 0..8 {
+    ///     load.push(&w1, i);
+    /// }
+    ///
+    /// let w2 = Worker::new_fifo();
+    /// let stolen = w1.stealer().steal_proportional(&w2, &load);
+    /// assert!(stolen.success().is_some());
+    /// ```
+    pub fn steal_proportional(&self, dest: &Worker<T>, load: &LoadBalancer) -> Steal<T> {
+        let len = self.len();
+        let mean = load.mean();
+
+        if len <= mean {
+            return Steal::Empty;
+        }
+
+        let n = cmp::min(len - mean, len / 2);
+        if n == 0 {
+            return Steal::Empty;
+        }
+
+        match self.steal_batch_and_pop_with_limit(dest, n) {
+            Steal::Success(task) => {
+                load.sub_one();
+                Steal::Success(task)
+            }
+            Steal::Empty => Steal::Empty,
+            Steal::Retry => Steal::Retry,
+        }
+    }
+}
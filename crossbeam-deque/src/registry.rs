@@ -0,0 +1,219 @@
+use std::fmt;
+
+use utils::sync::ShardedLock;
+
+use {Stealer, Worker};
+
+/// Marks the end of the free list.
+const NIL: usize = ::std::usize::MAX;
+
+enum Slot<T> {
+    Occupied(Stealer<T>),
+    /// Vacant, linking to the next free slot (or `NIL` if this was the last one freed).
+    Vacant(usize),
+}
+
+struct Inner<T> {
+    slots: Vec<Slot<T>>,
+    free: usize,
+}
+
+/// A dynamic registry of stealers for a worker pool that grows and shrinks at runtime.
+///
+/// Workers join the pool by calling [`register`], which stores the derived [`Stealer`] and
+/// returns a [`RegistrationGuard`] that removes it again on drop. Idle workers search for a
+/// victim by calling [`for_each`] or [`snapshot`]. The registry is backed by a sharded
+/// read-write lock, so the common case of many readers scanning for a victim while registrations
+/// change only occasionally stays close to contention-free; freed slots are recycled instead of
+/// leaving gaps that grow the backing storage without bound.
+///
+/// [`register`]: struct.StealerRegistry.html#method.register
+/// [`for_each`]: struct.StealerRegistry.html#method.for_each
+/// [`snapshot`]: struct.StealerRegistry.html#method.snapshot
+/// [`Stealer`]: struct.Stealer.html
+/// [`RegistrationGuard`]: struct.RegistrationGuard.html
+///
+/// # Examples
+///
+/// ```
+/// use crossbeam_deque::{StealerRegistry, Worker};
+///
+/// let registry = StealerRegistry::new();
+///
+/// let w = Worker::<i32>::new_fifo();
+/// let guard = registry.register(&w);
+///
+/// assert_eq!(registry.snapshot().len(), 1);
+/// drop(guard);
+/// assert_eq!(registry.snapshot().len(), 0);
+/// ```
+pub struct StealerRegistry<T> {
+    inner: ShardedLock<Inner<T>>,
+}
+
+impl<T> StealerRegistry<T> {
+    /// Creates an empty registry.
+    pub fn new() -> StealerRegistry<T> {
+        StealerRegistry {
+            inner: ShardedLock::new(Inner {
+                slots: Vec::new(),
+                free: NIL,
+            }),
+        }
+    }
+
+    /// Registers `worker`'s stealer, returning a guard that unregisters it when dropped.
+    pub fn register<'r>(&'r self, worker: &Worker<T>) -> RegistrationGuard<'r, T> {
+        let stealer = worker.stealer();
+        let mut inner = self.inner.write().unwrap();
+
+        let index = if inner.free != NIL {
+            let index = inner.free;
+            inner.free = match inner.slots[index] {
+                Slot::Vacant(next) => next,
+                Slot::Occupied(_) => unreachable!("free list points at an occupied slot"),
+            };
+            inner.slots[index] = Slot::Occupied(stealer);
+            index
+        } else {
+            inner.slots.push(Slot::Occupied(stealer));
+            inner.slots.len() - 1
+        };
+
+        RegistrationGuard {
+            registry: self,
+            index,
+        }
+    }
+
+    /// Calls `f` with every currently registered stealer.
+    pub fn for_each<F>(&self, mut f: F)
+    where
+        F: FnMut(&Stealer<T>),
+    {
+        let inner = self.inner.read().unwrap();
+        for slot in &inner.slots {
+            if let Slot::Occupied(ref stealer) = *slot {
+                f(stealer);
+            }
+        }
+    }
+
+    /// Returns a snapshot of every currently registered stealer.
+    pub fn snapshot(&self) -> Vec<Stealer<T>> {
+        let inner = self.inner.read().unwrap();
+        inner
+            .slots
+            .iter()
+            .filter_map(|slot| match *slot {
+                Slot::Occupied(ref stealer) => Some(stealer.clone()),
+                Slot::Vacant(_) => None,
+            })
+            .collect()
+    }
+
+    fn unregister(&self, index: usize) {
+        let mut inner = self.inner.write().unwrap();
+        let free = inner.free;
+        inner.slots[index] = Slot::Vacant(free);
+        inner.free = index;
+    }
+}
+
+impl<T> Default for StealerRegistry<T> {
+    fn default() -> StealerRegistry<T> {
+        StealerRegistry::new()
+    }
+}
+
+impl<T> fmt::Debug for StealerRegistry<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad("StealerRegistry { .. }")
+    }
+}
+
+/// A registration in a [`StealerRegistry`], created by [`StealerRegistry::register`].
+///
+/// Dropping the guard removes the associated stealer from the registry.
+///
+/// [`StealerRegistry`]: struct.StealerRegistry.html
+/// [`StealerRegistry::register`]: struct.StealerRegistry.html#method.register
+pub struct RegistrationGuard<'r, T: 'r> {
+    registry: &'r StealerRegistry<T>,
+    index: usize,
+}
+
+impl<'r, T> Drop for RegistrationGuard<'r, T> {
+    fn drop(&mut self) {
+        self.registry.unregister(self.index);
+    }
+}
+
+impl<'r, T> fmt::Debug for RegistrationGuard<'r, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad("RegistrationGuard { .. }")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{StealerRegistry, NIL};
+    use Worker;
+
+    #[test]
+    fn unregister_frees_the_slot_for_reuse() {
+        let registry = StealerRegistry::new();
+        let w = Worker::<i32>::new_fifo();
+
+        let g1 = registry.register(&w);
+        let index = g1.index;
+        drop(g1);
+
+        let g2 = registry.register(&w);
+        assert_eq!(
+            g2.index, index,
+            "a freed slot should be recycled instead of growing the backing storage"
+        );
+        assert_eq!(registry.inner.read().unwrap().slots.len(), 1);
+    }
+
+    #[test]
+    fn free_list_unwinds_in_lifo_order_across_several_slots() {
+        let registry = StealerRegistry::new();
+        let w = Worker::<i32>::new_fifo();
+
+        let g1 = registry.register(&w);
+        let g2 = registry.register(&w);
+        let g3 = registry.register(&w);
+
+        drop(g1);
+        drop(g2);
+
+        // The free list is LIFO: the most recently vacated slot (g2's) is handed out first.
+        let g4 = registry.register(&w);
+        assert_eq!(g4.index, 1);
+        let g5 = registry.register(&w);
+        assert_eq!(g5.index, 0);
+
+        drop(g3);
+        drop(g4);
+        drop(g5);
+        assert_eq!(registry.snapshot().len(), 0);
+        assert_eq!(registry.inner.read().unwrap().slots.len(), 3);
+        assert_ne!(registry.inner.read().unwrap().free, NIL);
+    }
+
+    #[test]
+    fn snapshot_reflects_registrations_and_unregistrations() {
+        let registry = StealerRegistry::new();
+        let w1 = Worker::<i32>::new_fifo();
+        let w2 = Worker::<i32>::new_fifo();
+
+        let g1 = registry.register(&w1);
+        let _g2 = registry.register(&w2);
+        assert_eq!(registry.snapshot().len(), 2);
+
+        drop(g1);
+        assert_eq!(registry.snapshot().len(), 1);
+    }
+}
@@ -1,9 +1,15 @@
-use epoch::with_handle;
+use std::fmt;
+
+use epoch::{with_handle, Guard};
 
 use deque_impl;
 
 pub use deque_impl::Steal;
 
+/// The default number of tasks moved by `steal_batch`/`steal_batch_and_pop` when no explicit
+/// limit is given: around half the victim queue, but not more than this constant.
+const DEFAULT_STEAL_LIMIT: usize = 32;
+
 /// A worker queue.
 ///
 /// This is a FIFO or LIFO queue that is owned by a single thread, but other threads may steal
@@ -109,6 +115,21 @@ impl<T> Worker<T> {
         self.0.is_empty()
     }
 
+    /// Returns the number of tasks in the queue.
+    ///
+    /// ```
+    /// use crossbeam_deque::Worker;
+    ///
+    /// let w = Worker::new_lifo();
+    ///
+    /// assert_eq!(w.len(), 0);
+    /// w.push(1);
+    /// assert_eq!(w.len(), 1);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
     /// Pushes a task into the queue.
     ///
     /// # Examples
@@ -142,6 +163,68 @@ impl<T> Worker<T> {
     pub fn pop(&self) -> Option<T> {
         with_handle(|h| self.0.pop(h))
     }
+
+    /// Runs a batch of operations against this queue under a single pinned epoch guard.
+    ///
+    /// Ordinary `push`/`pop` calls each pin the epoch on their own, which is wasted work when a
+    /// caller is about to perform many operations back to back (e.g. injecting a large batch of
+    /// tasks). `pinned` pins once and hands `f` a [`Pinned`] guard whose `push`/`pop` methods
+    /// reuse that same pin instead of acquiring a new one per call.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_deque::Worker;
+    ///
+    /// let w = Worker::new_fifo();
+    /// w.pinned(|p| {
+    ///     for i in 0..100 {
+    ///         p.push(i);
+    ///     }
+    /// });
+    /// assert_eq!(w.len(), 100);
+    /// ```
+    ///
+    /// [`Pinned`]: struct.Pinned.html
+    pub fn pinned<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&Pinned<T>) -> R,
+    {
+        let guard = with_handle(|handle| handle.pin());
+        f(&Pinned {
+            worker: self,
+            guard,
+        })
+    }
+}
+
+/// A scope that shares one pinned epoch guard across several operations on a [`Worker`].
+///
+/// Created by [`Worker::pinned`].
+///
+/// [`Worker`]: struct.Worker.html
+/// [`Worker::pinned`]: struct.Worker.html#method.pinned
+pub struct Pinned<'a, T: 'a> {
+    worker: &'a Worker<T>,
+    guard: Guard,
+}
+
+impl<'a, T> Pinned<'a, T> {
+    /// Pushes a task into the queue, reusing this scope's pinned guard.
+    pub fn push(&self, task: T) {
+        self.worker.0.push_pinned(task, &self.guard)
+    }
+
+    /// Pops a task from the queue, reusing this scope's pinned guard.
+    pub fn pop(&self) -> Option<T> {
+        self.worker.0.pop_pinned(&self.guard)
+    }
+}
+
+impl<'a, T> fmt::Debug for Pinned<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad("Pinned { .. }")
+    }
 }
 
 /// A stealer handle of a worker queue.
@@ -184,6 +267,26 @@ impl<T> Stealer<T> {
         self.0.is_empty()
     }
 
+    /// Returns the number of tasks in the queue.
+    ///
+    /// This is only an estimate: because other threads may concurrently push, pop, or steal
+    /// tasks, the returned value may be stale by the time it is observed. It saturates to `0`
+    /// rather than underflowing if a race makes the front and back indices appear inverted.
+    ///
+    /// ```
+    /// use crossbeam_deque::Worker;
+    ///
+    /// let w = Worker::new_lifo();
+    /// let s = w.stealer();
+    ///
+    /// assert_eq!(s.len(), 0);
+    /// w.push(1);
+    /// assert_eq!(s.len(), 1);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
     /// Steals a task from the queue.
     ///
     /// # Examples
@@ -227,7 +330,35 @@ impl<T> Stealer<T> {
     /// assert_eq!(w2.pop(), Some(2));
     /// ```
     pub fn steal_batch(&self, dest: &Worker<T>) -> Steal<()> {
-        with_handle(|h| self.0.steal_batch(&dest.0, h))
+        self.steal_batch_with_limit(dest, DEFAULT_STEAL_LIMIT)
+    }
+
+    /// Steals a batch of at most `limit` tasks and pushes them into another worker.
+    ///
+    /// How many tasks exactly will be stolen is not specified, other than that it will never be
+    /// more than `limit`. This is useful for schedulers that want to tune how aggressively a
+    /// worker drains a victim, e.g. based on the victim's length relative to the system load.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_deque::Worker;
+    ///
+    /// let w1 = Worker::new_fifo();
+    /// w1.push(1);
+    /// w1.push(2);
+    /// w1.push(3);
+    /// w1.push(4);
+    ///
+    /// let s = w1.stealer();
+    /// let w2 = Worker::new_fifo();
+    ///
+    /// s.steal_batch_with_limit(&w2, 1);
+    /// assert_eq!(w2.pop(), Some(1));
+    /// assert_eq!(w2.pop(), None);
+    /// ```
+    pub fn steal_batch_with_limit(&self, dest: &Worker<T>, limit: usize) -> Steal<()> {
+        with_handle(|h| self.0.steal_batch_with_limit(&dest.0, limit, h))
     }
 
     /// Steals a batch of tasks, pushes them into another worker, and pops a task from that worker.
@@ -253,7 +384,106 @@ impl<T> Stealer<T> {
     /// assert_eq!(w2.pop(), Some(2));
     /// ```
     pub fn steal_batch_and_pop(&self, dest: &Worker<T>) -> Steal<T> {
-        with_handle(|h| self.0.steal_batch_and_pop(&dest.0, h))
+        self.steal_batch_and_pop_with_limit(dest, DEFAULT_STEAL_LIMIT)
+    }
+
+    /// Steals a batch of at most `limit` tasks, pushes them into another worker, and pops a task
+    /// from that worker.
+    ///
+    /// How many tasks exactly will be stolen is not specified, other than that it will never be
+    /// more than `limit`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_deque::{Steal, Worker};
+    ///
+    /// let w1 = Worker::new_fifo();
+    /// w1.push(1);
+    /// w1.push(2);
+    /// w1.push(3);
+    /// w1.push(4);
+    ///
+    /// let s = w1.stealer();
+    /// let w2 = Worker::new_fifo();
+    ///
+    /// assert_eq!(s.steal_batch_and_pop_with_limit(&w2, 1), Steal::Success(1));
+    /// assert_eq!(w2.pop(), None);
+    /// ```
+    pub fn steal_batch_and_pop_with_limit(&self, dest: &Worker<T>, limit: usize) -> Steal<T> {
+        with_handle(|h| self.0.steal_batch_and_pop_with_limit(&dest.0, limit, h))
+    }
+
+    /// Runs a batch of steal operations against this queue under a single pinned epoch guard.
+    ///
+    /// Useful for a tight steal loop that would otherwise pin the epoch on every attempt.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_deque::{Steal, Worker};
+    ///
+    /// let w1 = Worker::new_fifo();
+    /// w1.push(1);
+    /// w1.push(2);
+    ///
+    /// let s = w1.stealer();
+    /// let stolen = s.pinned(|p| {
+    ///     while let Steal::Retry = p.steal() {}
+    ///     p.steal()
+    /// });
+    /// ```
+    ///
+    /// [`StealerPinned`]: struct.StealerPinned.html
+    pub fn pinned<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&StealerPinned<T>) -> R,
+    {
+        let guard = with_handle(|handle| handle.pin());
+        f(&StealerPinned {
+            stealer: self,
+            guard,
+        })
+    }
+}
+
+/// A scope that shares one pinned epoch guard across several steal operations on a [`Stealer`].
+///
+/// Created by [`Stealer::pinned`].
+///
+/// [`Stealer`]: struct.Stealer.html
+/// [`Stealer::pinned`]: struct.Stealer.html#method.pinned
+pub struct StealerPinned<'a, T: 'a> {
+    stealer: &'a Stealer<T>,
+    guard: Guard,
+}
+
+impl<'a, T> StealerPinned<'a, T> {
+    /// Steals a task from the queue, reusing this scope's pinned guard.
+    pub fn steal(&self) -> Steal<T> {
+        self.stealer.0.steal_pinned(&self.guard)
+    }
+
+    /// Steals a batch of at most `limit` tasks and pushes them into `dest`, reusing this scope's
+    /// pinned guard.
+    pub fn steal_batch_with_limit(&self, dest: &Worker<T>, limit: usize) -> Steal<()> {
+        self.stealer
+            .0
+            .steal_batch_with_limit_pinned(&dest.0, limit, &self.guard)
+    }
+
+    /// Steals a batch of at most `limit` tasks, pushes them into `dest`, and pops one, reusing
+    /// this scope's pinned guard.
+    pub fn steal_batch_and_pop_with_limit(&self, dest: &Worker<T>, limit: usize) -> Steal<T> {
+        self.stealer
+            .0
+            .steal_batch_and_pop_with_limit_pinned(&dest.0, limit, &self.guard)
+    }
+}
+
+impl<'a, T> fmt::Debug for StealerPinned<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad("StealerPinned { .. }")
     }
 }
 
@@ -348,7 +578,32 @@ impl<T> Injector<T> {
     /// assert_eq!(w.pop(), Some(2));
     /// ```
     pub fn steal_batch(&self, dest: &Worker<T>) -> Steal<()> {
-        with_handle(|h| self.0.steal_batch(&dest.0, h))
+        self.steal_batch_with_limit(dest, DEFAULT_STEAL_LIMIT)
+    }
+
+    /// Steals a batch of at most `limit` tasks and pushes them into a worker.
+    ///
+    /// How many tasks exactly will be stolen is not specified, other than that it will never be
+    /// more than `limit`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_deque::{Injector, Worker};
+    ///
+    /// let q = Injector::new();
+    /// q.push(1);
+    /// q.push(2);
+    /// q.push(3);
+    /// q.push(4);
+    ///
+    /// let w = Worker::new_fifo();
+    /// q.steal_batch_with_limit(&w, 1);
+    /// assert_eq!(w.pop(), Some(1));
+    /// assert_eq!(w.pop(), None);
+    /// ```
+    pub fn steal_batch_with_limit(&self, dest: &Worker<T>, limit: usize) -> Steal<()> {
+        with_handle(|h| self.0.steal_batch_with_limit(&dest.0, limit, h))
     }
 
     /// Steals a batch of tasks, pushes them into a worker, and pops a task from that worker.
@@ -372,7 +627,32 @@ impl<T> Injector<T> {
     /// assert_eq!(w.pop(), Some(2));
     /// ```
     pub fn steal_batch_and_pop(&self, dest: &Worker<T>) -> Steal<T> {
-        with_handle(|h| self.0.steal_batch_and_pop(&dest.0, h))
+        self.steal_batch_and_pop_with_limit(dest, DEFAULT_STEAL_LIMIT)
+    }
+
+    /// Steals a batch of at most `limit` tasks, pushes them into a worker, and pops a task from
+    /// that worker.
+    ///
+    /// How many tasks exactly will be stolen is not specified, other than that it will never be
+    /// more than `limit`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_deque::{Injector, Steal, Worker};
+    ///
+    /// let q = Injector::new();
+    /// q.push(1);
+    /// q.push(2);
+    /// q.push(3);
+    /// q.push(4);
+    ///
+    /// let w = Worker::new_fifo();
+    /// assert_eq!(q.steal_batch_and_pop_with_limit(&w, 1), Steal::Success(1));
+    /// assert_eq!(w.pop(), None);
+    /// ```
+    pub fn steal_batch_and_pop_with_limit(&self, dest: &Worker<T>, limit: usize) -> Steal<T> {
+        with_handle(|h| self.0.steal_batch_and_pop_with_limit(&dest.0, limit, h))
     }
 
     /// Returns `true` if the queue is empty.
@@ -391,4 +671,91 @@ impl<T> Injector<T> {
     pub fn is_empty(&self) -> bool {
         self.0.is_empty()
     }
+
+    /// Returns the number of tasks in the queue.
+    ///
+    /// This is only an estimate: because other threads may concurrently push or steal tasks, the
+    /// returned value may be stale by the time it is observed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_deque::Injector;
+    ///
+    /// let q = Injector::new();
+    ///
+    /// assert_eq!(q.len(), 0);
+    /// q.push(1);
+    /// assert_eq!(q.len(), 1);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Runs a batch of steal operations against this queue under a single pinned epoch guard.
+    ///
+    /// Useful when draining many tasks out of the injector at once, e.g. while bulk-spawning: each
+    /// steal call otherwise pins the epoch on its own.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossbeam_deque::{Injector, Worker};
+    ///
+    /// let q = Injector::new();
+    /// for i in 0..64 {
+    ///     q.push(i);
+    /// }
+    ///
+    /// let w = Worker::new_fifo();
+    /// q.pinned(|p| {
+    ///     p.steal_batch_with_limit(&w, 32);
+    /// });
+    /// ```
+    pub fn pinned<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&InjectorPinned<T>) -> R,
+    {
+        let guard = with_handle(|handle| handle.pin());
+        f(&InjectorPinned {
+            injector: self,
+            guard,
+        })
+    }
+}
+
+/// A scope that shares one pinned epoch guard across several steal operations on an
+/// [`Injector`].
+///
+/// Created by [`Injector::pinned`].
+///
+/// [`Injector`]: struct.Injector.html
+/// [`Injector::pinned`]: struct.Injector.html#method.pinned
+pub struct InjectorPinned<'a, T: 'a> {
+    injector: &'a Injector<T>,
+    guard: Guard,
+}
+
+impl<'a, T> InjectorPinned<'a, T> {
+    /// Steals a batch of at most `limit` tasks and pushes them into `dest`, reusing this scope's
+    /// pinned guard.
+    pub fn steal_batch_with_limit(&self, dest: &Worker<T>, limit: usize) -> Steal<()> {
+        self.injector
+            .0
+            .steal_batch_with_limit_pinned(&dest.0, limit, &self.guard)
+    }
+
+    /// Steals a batch of at most `limit` tasks, pushes them into `dest`, and pops one, reusing
+    /// this scope's pinned guard.
+    pub fn steal_batch_and_pop_with_limit(&self, dest: &Worker<T>, limit: usize) -> Steal<T> {
+        self.injector
+            .0
+            .steal_batch_and_pop_with_limit_pinned(&dest.0, limit, &self.guard)
+    }
+}
+
+impl<'a, T> fmt::Debug for InjectorPinned<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad("InjectorPinned { .. }")
+    }
 }